@@ -2,21 +2,232 @@ use axum::{
     Router,
     body::Body,
     extract::{Query, State},
-    http::{Response, StatusCode, header},
+    http::{HeaderMap, Response, StatusCode, header},
     response::IntoResponse,
 };
 use reqwest::Client;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tauri::Manager;
 use tokio::sync::RwLock;
 
-/// Shared state for the proxy server and Tauri commands.
+/// Shared state for the proxy protocol handler and Tauri commands.
 struct AppState {
-    /// HTTP client for proxying
-    client: Client,
-    /// The port the proxy is listening on
-    proxy_port: RwLock<u16>,
+    /// HTTP client for proxying, rebuilt in place whenever the upstream
+    /// proxy setting changes so callers never need to re-fetch `AppState`.
+    client: RwLock<Client>,
+    /// Per-source headers/credentials, cached from `config.json` so the
+    /// proxy handler doesn't have to hit disk on every request.
+    source_credentials: RwLock<Vec<SourceCredentials>>,
+}
+
+/// Custom headers and/or basic-auth credentials to apply to requests whose
+/// URL matches `host_pattern`, persisted under the `source_credentials` key
+/// in `config.json`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct SourceCredentials {
+    /// Host this entry applies to, e.g. `example.com`. Matched against the
+    /// request URL's host only — either exactly or as a real subdomain
+    /// (`example.com` also matches `api.example.com`) — never against the
+    /// scheme, port, or path, so a URL like `https://example.com:8080/live`
+    /// should not be used here.
+    host_pattern: String,
+    #[serde(default)]
+    headers: std::collections::HashMap<String, String>,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+/// Find the stored credentials whose `host_pattern` matches `url`'s host, if
+/// any. Matches the exact host or a real subdomain of it (`example.com`
+/// matches `api.example.com` but not `example.com.attacker.tld`), never a
+/// substring of the full URL — otherwise a query string or path segment
+/// could smuggle credentials to an unrelated host.
+fn find_source_credentials<'a>(
+    all: &'a [SourceCredentials],
+    url: &str,
+) -> Option<&'a SourceCredentials> {
+    let host = url::Url::parse(url).ok()?.host_str()?.to_string();
+    all.iter().find(|c| {
+        host == c.host_pattern || host.ends_with(&format!(".{}", c.host_pattern))
+    })
+}
+
+/// Apply matching per-source headers and basic-auth credentials to an
+/// outbound request builder.
+fn apply_source_credentials(
+    mut req: reqwest::RequestBuilder,
+    creds: Option<&SourceCredentials>,
+) -> reqwest::RequestBuilder {
+    let Some(creds) = creds else { return req };
+
+    for (key, value) in &creds.headers {
+        req = req.header(key, value);
+    }
+    if let Some(username) = &creds.username {
+        req = req.basic_auth(username, creds.password.clone());
+    }
+    req
+}
+
+/// Upstream HTTP/SOCKS5 proxy settings, persisted under the `upstream_proxy`
+/// key in `config.json`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct UpstreamProxyConfig {
+    /// Proxy URL, e.g. `http://host:port` or `socks5://host:port`.
+    url: String,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+/// Client TLS identity (mTLS) and optional custom CA bundle, persisted
+/// under the `tls_identity` key in `config.json`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct TlsIdentityConfig {
+    /// Path to a PKCS#12 (.p12/.pfx) bundle containing the client cert + key.
+    pkcs12_path: Option<String>,
+    pkcs12_password: Option<String>,
+    /// Path to a PEM file containing both the client certificate and private key.
+    pem_identity_path: Option<String>,
+    /// Path to a PEM-encoded CA bundle to additionally trust.
+    ca_bundle_path: Option<String>,
+}
+
+/// Subject and expiry of the active client certificate, surfaced to the UI
+/// so users can confirm the right identity is loaded.
+#[derive(Debug, Clone, Serialize)]
+struct TlsIdentityInfo {
+    subject: String,
+    not_after: String,
+}
+
+/// Parse a PEM-encoded certificate's subject and expiry.
+fn describe_pem_cert(pem_bytes: &[u8]) -> Result<TlsIdentityInfo, String> {
+    let (_, pem) = x509_parser::pem::parse_x509_pem(pem_bytes)
+        .map_err(|e| format!("Failed to parse certificate: {}", e))?;
+    let cert = pem
+        .parse_x509()
+        .map_err(|e| format!("Failed to parse certificate: {}", e))?;
+    Ok(TlsIdentityInfo {
+        subject: cert.subject().to_string(),
+        not_after: cert.validity().not_after.to_string(),
+    })
+}
+
+/// Parse the subject and expiry of the first certificate in a PKCS#12
+/// bundle. The bundle's certificates are taken in stored order; the leaf is
+/// not picked out by matching it to the private key.
+fn describe_pkcs12_cert(der_bytes: &[u8], password: &str) -> Result<TlsIdentityInfo, String> {
+    let pfx = p12::PFX::parse(der_bytes).map_err(|e| format!("Invalid PKCS#12 bundle: {:?}", e))?;
+    let cert_der = pfx
+        .cert_bags(password)
+        .map_err(|e| format!("Invalid PKCS#12 password: {:?}", e))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "PKCS#12 bundle contains no certificate".to_string())?;
+    let (_, cert) = x509_parser::parse_x509_certificate(&cert_der)
+        .map_err(|e| format!("Failed to parse certificate: {}", e))?;
+    Ok(TlsIdentityInfo {
+        subject: cert.subject().to_string(),
+        not_after: cert.validity().not_after.to_string(),
+    })
+}
+
+/// Describe the active client certificate (if any) for display in the UI.
+fn describe_tls_identity(tls: &TlsIdentityConfig) -> Result<Option<TlsIdentityInfo>, String> {
+    if let Some(path) = &tls.pkcs12_path {
+        let der = std::fs::read(path).map_err(|e| format!("Failed to read PKCS#12 bundle: {}", e))?;
+        return describe_pkcs12_cert(&der, tls.pkcs12_password.as_deref().unwrap_or("")).map(Some);
+    }
+    if let Some(path) = &tls.pem_identity_path {
+        let pem = std::fs::read(path).map_err(|e| format!("Failed to read PEM identity: {}", e))?;
+        return describe_pem_cert(&pem).map(Some);
+    }
+    Ok(None)
+}
+
+/// Build a `reqwest::Client` that routes all traffic through `proxy` and
+/// presents `tls` as its client identity, when configured.
+fn build_client(
+    proxy: Option<&UpstreamProxyConfig>,
+    tls: Option<&TlsIdentityConfig>,
+) -> Result<Client, String> {
+    let mut builder = Client::builder();
+
+    if let Some(proxy) = proxy {
+        let mut p = reqwest::Proxy::all(&proxy.url).map_err(|e| format!("Invalid proxy URL: {}", e))?;
+        if let Some(username) = &proxy.username {
+            p = p.basic_auth(username, proxy.password.as_deref().unwrap_or(""));
+        }
+        builder = builder.proxy(p);
+    }
+
+    if let Some(tls) = tls {
+        if let Some(path) = &tls.pkcs12_path {
+            let der = std::fs::read(path).map_err(|e| format!("Failed to read PKCS#12 bundle: {}", e))?;
+            let identity = reqwest::Identity::from_pkcs12_der(&der, tls.pkcs12_password.as_deref().unwrap_or(""))
+                .map_err(|e| format!("Invalid PKCS#12 bundle: {}", e))?;
+            builder = builder.identity(identity);
+        } else if let Some(path) = &tls.pem_identity_path {
+            let pem = std::fs::read(path).map_err(|e| format!("Failed to read PEM identity: {}", e))?;
+            let identity =
+                reqwest::Identity::from_pem(&pem).map_err(|e| format!("Invalid PEM identity: {}", e))?;
+            builder = builder.identity(identity);
+        }
+
+        if let Some(path) = &tls.ca_bundle_path {
+            let ca = std::fs::read(path).map_err(|e| format!("Failed to read CA bundle: {}", e))?;
+            let cert = reqwest::Certificate::from_pem(&ca).map_err(|e| format!("Invalid CA bundle: {}", e))?;
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    builder
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))
+}
+
+/// Rebuild a `Client` from the upstream-proxy and TLS-identity settings
+/// currently stored in `config.json`.
+fn build_client_from_config(config: &serde_json::Value) -> Result<Client, String> {
+    let proxy = config
+        .get("upstream_proxy")
+        .and_then(|v| serde_json::from_value::<UpstreamProxyConfig>(v.clone()).ok());
+    let tls = config
+        .get("tls_identity")
+        .and_then(|v| serde_json::from_value::<TlsIdentityConfig>(v.clone()).ok());
+    build_client(proxy.as_ref(), tls.as_ref())
+}
+
+/// Read `config.json` from the app data directory as a JSON value.
+fn read_config_json(app_handle: &tauri::AppHandle) -> Result<serde_json::Value, String> {
+    let data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+
+    let config_path = data_dir.join("config.json");
+    if !config_path.exists() {
+        return Ok(serde_json::json!({}));
+    }
+
+    let raw =
+        std::fs::read_to_string(&config_path).map_err(|e| format!("Failed to read config: {}", e))?;
+    serde_json::from_str(&raw).map_err(|e| format!("Failed to parse config: {}", e))
+}
+
+/// Write a JSON value back to `config.json` in the app data directory.
+fn write_config_json(app_handle: &tauri::AppHandle, config: &serde_json::Value) -> Result<(), String> {
+    let data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+
+    std::fs::create_dir_all(&data_dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
+
+    let config_path = data_dir.join("config.json");
+    let raw = serde_json::to_string_pretty(config).map_err(|e| format!("Failed to serialize config: {}", e))?;
+    std::fs::write(&config_path, raw).map_err(|e| format!("Failed to write config: {}", e))
 }
 
 /// Tauri command: fetch a URL from the Rust backend (for M3U playlist loading).
@@ -25,9 +236,12 @@ async fn fetch_url(
     state: tauri::State<'_, Arc<AppState>>,
     url: String,
 ) -> Result<String, String> {
-    let response = state
-        .client
-        .get(&url)
+    let creds = state.source_credentials.read().await;
+    let req = apply_source_credentials(
+        state.client.read().await.get(&url),
+        find_source_credentials(&creds, &url),
+    );
+    let response = req
         .send()
         .await
         .map_err(|e| format!("Network error: {}", e))?;
@@ -46,13 +260,6 @@ async fn fetch_url(
         .map_err(|e| format!("Failed to read response: {}", e))
 }
 
-/// Tauri command: return the proxy port.
-#[tauri::command]
-async fn get_proxy_port(state: tauri::State<'_, Arc<AppState>>) -> Result<u16, String> {
-    let port = state.proxy_port.read().await;
-    Ok(*port)
-}
-
 /// Tauri command: read config.json from the app data directory.
 #[tauri::command]
 async fn read_config(app_handle: tauri::AppHandle) -> Result<String, String> {
@@ -88,6 +295,104 @@ async fn write_config(app_handle: tauri::AppHandle, data: String) -> Result<(),
         .map_err(|e| format!("Failed to write config: {}", e))
 }
 
+/// Tauri command: set (or clear) the upstream proxy that all outbound
+/// playlist/segment requests are routed through, and rebuild the shared
+/// client in place so the change applies without restarting the proxy.
+#[tauri::command]
+async fn set_upstream_proxy(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    proxy: Option<UpstreamProxyConfig>,
+) -> Result<(), String> {
+    let mut config = read_config_json(&app_handle)?;
+    match &proxy {
+        Some(proxy) => {
+            config["upstream_proxy"] = serde_json::to_value(proxy)
+                .map_err(|e| format!("Failed to serialize proxy config: {}", e))?;
+        }
+        None => {
+            if let Some(map) = config.as_object_mut() {
+                map.remove("upstream_proxy");
+            }
+        }
+    }
+
+    // Build (and validate) the client before persisting, so a bad proxy URL
+    // never ends up in config.json without the command reporting an error.
+    let new_client = build_client_from_config(&config)?;
+    write_config_json(&app_handle, &config)?;
+
+    *state.client.write().await = new_client;
+    Ok(())
+}
+
+/// Tauri command: set (or clear) the mTLS client identity and custom CA
+/// bundle used for outbound requests, and rebuild the shared client in
+/// place. Returns the decoded certificate subject/expiry so the UI can
+/// confirm the right identity is loaded.
+#[tauri::command]
+async fn set_tls_identity(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    identity: Option<TlsIdentityConfig>,
+) -> Result<Option<TlsIdentityInfo>, String> {
+    let mut config = read_config_json(&app_handle)?;
+    match &identity {
+        Some(identity) => {
+            config["tls_identity"] = serde_json::to_value(identity)
+                .map_err(|e| format!("Failed to serialize TLS identity: {}", e))?;
+        }
+        None => {
+            if let Some(map) = config.as_object_mut() {
+                map.remove("tls_identity");
+            }
+        }
+    }
+    // Build (and validate) the client before persisting, so a bad PKCS#12
+    // password or an unreadable cert/CA path never ends up in config.json
+    // without the command reporting an error.
+    let new_client = build_client_from_config(&config)?;
+    write_config_json(&app_handle, &config)?;
+
+    *state.client.write().await = new_client;
+
+    match &identity {
+        Some(identity) => describe_tls_identity(identity),
+        None => Ok(None),
+    }
+}
+
+/// Tauri command: save (or replace) the headers/credentials used for
+/// requests matching a given host pattern.
+#[tauri::command]
+async fn save_source_credentials(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    credentials: SourceCredentials,
+) -> Result<(), String> {
+    let mut config = read_config_json(&app_handle)?;
+    let mut all: Vec<SourceCredentials> = config
+        .get("source_credentials")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default();
+
+    // `find_source_credentials` compares against `url::Url::host_str()`, which the
+    // WHATWG URL spec always returns lowercased, so store the pattern lowercased
+    // too or an uppercase host (e.g. `MyProvider.TV`) would silently never match.
+    let mut credentials = credentials;
+    credentials.host_pattern = credentials.host_pattern.to_lowercase();
+
+    all.retain(|c| c.host_pattern != credentials.host_pattern);
+    all.push(credentials);
+
+    config["source_credentials"] =
+        serde_json::to_value(&all).map_err(|e| format!("Failed to serialize credentials: {}", e))?;
+    write_config_json(&app_handle, &config)?;
+
+    *state.source_credentials.write().await = all;
+    Ok(())
+}
+
 /// Query params for the proxy endpoint.
 #[derive(Deserialize)]
 struct ProxyQuery {
@@ -105,8 +410,13 @@ fn resolve_url(base: &url::Url, raw: &str) -> String {
     }
 }
 
+/// Wrap an absolute URL so the `stream://` custom protocol handler proxies it.
+fn to_stream_proxy_url(abs: &str) -> String {
+    format!("stream://proxy?url={}", urlencoding::encode(abs))
+}
+
 /// Rewrite an m3u8 manifest so every URL line goes through the proxy.
-fn rewrite_manifest(content: &str, manifest_url: &str, proxy_port: u16) -> String {
+fn rewrite_manifest(content: &str, manifest_url: &str) -> String {
     let base = match url::Url::parse(manifest_url) {
         Ok(u) => u,
         Err(_) => return content.to_string(),
@@ -125,11 +435,7 @@ fn rewrite_manifest(content: &str, manifest_url: &str, proxy_port: u16) -> Strin
                         if let Some(end) = result[uri_start..].find('"') {
                             let uri = &result[uri_start..uri_start + end].to_string();
                             let abs = resolve_url(&base, uri);
-                            let proxy = format!(
-                                "http://127.0.0.1:{}/proxy?url={}",
-                                proxy_port,
-                                urlencoding::encode(&abs)
-                            );
+                            let proxy = to_stream_proxy_url(&abs);
                             result = format!(
                                 "{}URI=\"{}\"{}",
                                 &line[..start],
@@ -145,11 +451,7 @@ fn rewrite_manifest(content: &str, manifest_url: &str, proxy_port: u16) -> Strin
             } else {
                 // This is a URL line — resolve it and wrap in proxy
                 let abs = resolve_url(&base, trimmed);
-                format!(
-                    "http://127.0.0.1:{}/proxy?url={}",
-                    proxy_port,
-                    urlencoding::encode(&abs)
-                )
+                to_stream_proxy_url(&abs)
             }
         })
         .collect::<Vec<_>>()
@@ -161,15 +463,26 @@ fn rewrite_manifest(content: &str, manifest_url: &str, proxy_port: u16) -> Strin
 async fn proxy_handler(
     State(state): State<Arc<AppState>>,
     Query(params): Query<ProxyQuery>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     let target_url = &params.url;
-    let proxy_port = *state.proxy_port.read().await;
 
     let is_manifest = target_url.ends_with(".m3u8")
         || target_url.contains(".m3u8?")
         || target_url.ends_with(".m3u");
 
-    match state.client.get(target_url).send().await {
+    let client = state.client.read().await.clone();
+    let creds = state.source_credentials.read().await;
+    let mut upstream_req =
+        apply_source_credentials(client.get(target_url), find_source_credentials(&creds, target_url));
+    if let Some(range) = headers.get(header::RANGE) {
+        upstream_req = upstream_req.header(header::RANGE, range);
+    }
+    if let Some(if_range) = headers.get(header::IF_RANGE) {
+        upstream_req = upstream_req.header(header::IF_RANGE, if_range);
+    }
+
+    match upstream_req.send().await {
         Ok(resp) => {
             let status = StatusCode::from_u16(resp.status().as_u16())
                 .unwrap_or(StatusCode::BAD_GATEWAY);
@@ -203,7 +516,7 @@ async fn proxy_handler(
                 };
 
                 let text = String::from_utf8_lossy(&body_bytes);
-                let rewritten = rewrite_manifest(&text, &final_url, proxy_port);
+                let rewritten = rewrite_manifest(&text, &final_url);
 
                 Response::builder()
                     .status(status)
@@ -214,18 +527,33 @@ async fn proxy_handler(
                     .body(Body::from(rewritten))
                     .unwrap()
             } else {
-                // Stream the response body directly (essential for live TS streams)
-                let stream = resp.bytes_stream();
-                let body = Body::from_stream(stream);
-
-                Response::builder()
+                // Preserve the upstream status (206 Partial Content / 416 Range Not
+                // Satisfiable) and range headers so seeking in the <video> element works.
+                let mut builder = Response::builder()
                     .status(status)
                     .header(header::CONTENT_TYPE, &content_type)
                     .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
                     .header(header::ACCESS_CONTROL_ALLOW_METHODS, "GET, OPTIONS")
-                    .header(header::ACCESS_CONTROL_ALLOW_HEADERS, "*")
-                    .body(body)
-                    .unwrap()
+                    .header(header::ACCESS_CONTROL_ALLOW_HEADERS, "*");
+
+                for name in [
+                    header::CONTENT_RANGE,
+                    header::ACCEPT_RANGES,
+                    header::CONTENT_LENGTH,
+                ] {
+                    if let Some(value) = resp.headers().get(&name) {
+                        builder = builder.header(name, value);
+                    }
+                }
+
+                // Stream from reqwest rather than buffering the whole upstream
+                // response in memory first. Note this only helps up to the
+                // `stream://` protocol boundary: `to_tauri_response` still has
+                // to collect the final bytes, since `UriSchemeResponder` has no
+                // chunked/streaming `respond` variant to hand this off to.
+                let body = Body::from_stream(resp.bytes_stream());
+
+                builder.body(body).unwrap()
             }
         }
         Err(e) => Response::builder()
@@ -236,34 +564,73 @@ async fn proxy_handler(
     }
 }
 
-/// Start the local HTTP proxy server on a random port.
-async fn start_proxy_server(state: Arc<AppState>) -> u16 {
-    let app = Router::new()
+/// Build the axum router that serves proxied requests. Shared by the
+/// `stream://` custom protocol handler below.
+fn build_router(state: Arc<AppState>) -> Router {
+    Router::new()
         .route("/proxy", axum::routing::get(proxy_handler))
-        .with_state(state);
-
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
-        .await
-        .expect("Failed to bind proxy server");
-
-    let port = listener.local_addr().unwrap().port();
-    println!("Stream proxy started on http://127.0.0.1:{}", port);
+        // `stream://proxy?url=...` arrives with host `proxy` and path `/`.
+        .route("/", axum::routing::get(proxy_handler))
+        .with_state(state)
+}
 
-    tokio::spawn(async move {
-        axum::serve(listener, app).await.unwrap();
-    });
+/// Convert an incoming `stream://` protocol request into the axum request
+/// type the router expects.
+fn to_axum_request(request: tauri::http::Request<Vec<u8>>) -> axum::extract::Request {
+    let (parts, body) = request.into_parts();
+    axum::extract::Request::from_parts(parts, Body::from(body))
+}
 
-    port
+/// Largest body `to_tauri_response` will buffer in memory. `stream://`
+/// responses are always fully buffered (see below), so without a cap a
+/// single large VOD segment or a live stream with no `Content-Length` could
+/// grow the buffer without bound; 64 MiB comfortably covers a real TS/VOD
+/// segment while still failing fast on anything pathological.
+const MAX_BUFFERED_RESPONSE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Convert the router's response back into the `Vec<u8>`-bodied response the
+/// custom protocol responder expects. `UriSchemeResponder::respond` only
+/// takes a complete `http::Response<Vec<u8>>` — there's no streaming/chunked
+/// variant — so every response, manifest or segment, is fully buffered here
+/// regardless of size, up to `MAX_BUFFERED_RESPONSE_BYTES`; anything larger
+/// is rejected rather than buffered without limit. True end-to-end streaming
+/// would need Tauri to expose a chunked responder; until then this is an
+/// inherent limitation of serving proxied media through a custom protocol
+/// instead of the old TCP listener.
+async fn to_tauri_response(response: Response<Body>) -> tauri::http::Response<Vec<u8>> {
+    let (parts, body) = response.into_parts();
+    match axum::body::to_bytes(body, MAX_BUFFERED_RESPONSE_BYTES).await {
+        Ok(bytes) => tauri::http::Response::from_parts(parts, bytes.to_vec()),
+        Err(e) => {
+            // `to_bytes` also returns `Err` when the upstream body errors mid-stream
+            // (e.g. a connection reset while reading a live TS segment), which has
+            // nothing to do with the size cap — only report 413 for an actual
+            // length-limit hit, not every buffering failure.
+            let message = e.to_string();
+            if message.contains("length limit exceeded") {
+                tauri::http::Response::builder()
+                    .status(tauri::http::StatusCode::PAYLOAD_TOO_LARGE)
+                    .body(b"Response too large to buffer through stream://".to_vec())
+                    .unwrap()
+            } else {
+                tauri::http::Response::builder()
+                    .status(tauri::http::StatusCode::BAD_GATEWAY)
+                    .body(format!("Failed to read response: {}", message).into_bytes())
+                    .unwrap()
+            }
+        }
+    }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let state = Arc::new(AppState {
-        client: Client::new(),
-        proxy_port: RwLock::new(0),
+        client: RwLock::new(Client::new()),
+        source_credentials: RwLock::new(Vec::new()),
     });
 
     let state_clone = state.clone();
+    let router = build_router(state.clone());
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
@@ -284,18 +651,62 @@ pub fn run() {
             }
 
             let state = state_clone.clone();
-            tauri::async_runtime::spawn(async move {
-                let port = start_proxy_server(state.clone()).await;
-                let mut proxy_port = state.proxy_port.write().await;
-                *proxy_port = port;
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::block_on(async move {
+                // Restore the upstream proxy / TLS identity settings (if
+                // any) before the protocol handler starts serving requests.
+                if let Ok(config) = read_config_json(&app_handle) {
+                    if let Ok(client) = build_client_from_config(&config) {
+                        *state.client.write().await = client;
+                    }
+
+                    if let Some(creds) = config
+                        .get("source_credentials")
+                        .and_then(|v| serde_json::from_value::<Vec<SourceCredentials>>(v.clone()).ok())
+                    {
+                        *state.source_credentials.write().await = creds;
+                    }
+                }
             });
             Ok(())
         })
+        .register_asynchronous_uri_scheme_protocol("stream", move |_app, request, responder| {
+            let mut router = router.clone();
+            tauri::async_runtime::spawn(async move {
+                use tower::Service;
+
+                let axum_request = to_axum_request(request);
+                let service = match router.ready().await {
+                    Ok(service) => service,
+                    Err(e) => {
+                        responder.respond(
+                            tauri::http::Response::builder()
+                                .status(tauri::http::StatusCode::BAD_GATEWAY)
+                                .body(format!("Proxy router unavailable: {}", e).into_bytes())
+                                .unwrap(),
+                        );
+                        return;
+                    }
+                };
+
+                match service.call(axum_request).await {
+                    Ok(response) => responder.respond(to_tauri_response(response).await),
+                    Err(e) => responder.respond(
+                        tauri::http::Response::builder()
+                            .status(tauri::http::StatusCode::BAD_GATEWAY)
+                            .body(format!("Proxy error: {}", e).into_bytes())
+                            .unwrap(),
+                    ),
+                }
+            });
+        })
         .invoke_handler(tauri::generate_handler![
             fetch_url,
-            get_proxy_port,
             read_config,
-            write_config
+            write_config,
+            set_upstream_proxy,
+            save_source_credentials,
+            set_tls_identity
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");